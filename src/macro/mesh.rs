@@ -12,9 +12,11 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
+use std::cell::RefCell;
 use std::fmt;
 use std::from_str::FromStr;
 use std::gc::Gc;
+use std::rc::Rc;
 use syntax::{ast, ext};
 use syntax::ext::build::AstBuilder;
 use syntax::ext::deriving::generic;
@@ -37,6 +39,11 @@ enum Modifier {
     ///
     /// Casts the component to a double precision floating-point number at runtime.
     AsDouble,
+    /// Corresponds to the `#[skip]` attribute.
+    ///
+    /// Omits the field from the generated attribute list, though its size
+    /// still contributes to later fields' offsets.
+    Skip,
 }
 
 impl fmt::Show for Modifier {
@@ -45,6 +52,7 @@ impl fmt::Show for Modifier {
             Normalized => write!(f, "normalized"),
             AsFloat => write!(f, "as_float"),
             AsDouble => write!(f, "as_double"),
+            Skip => write!(f, "skip"),
         }
     }
 }
@@ -55,11 +63,22 @@ impl FromStr for Modifier {
             "normalized" => Some(Normalized),
             "as_float" => Some(AsFloat),
             "as_double" => Some(AsDouble),
+            "skip" => Some(Skip),
             _ => None,
         }
     }
 }
 
+/// Whether an integer component type is signed, derived from its `i`/`u`
+/// prefix. Kept separate from `Modifier` since signedness and modifier
+/// (normalized/as_float/...) are independent axes of `decode_type`'s
+/// decision table.
+#[deriving(PartialEq)]
+enum Sign {
+    Signed,
+    Unsigned,
+}
+
 /// Scan through the field's attributes and extract a relevant modifier. If
 /// multiple modifier attributes are found, use the first modifier and emit a
 /// warning.
@@ -84,6 +103,57 @@ fn find_modifier(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
     })
 }
 
+/// Scan the field's attributes for a `#[name = "..."]` override.
+fn find_name_override(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
+                      attributes: &[ast::Attribute]) -> Option<String> {
+    attributes.iter().fold(None, |name, attribute| {
+        match attribute.node.value.node {
+            ast::MetaNameValue(ref word, ref lit) if word.get() == "name" => {
+                match lit.node {
+                    ast::LitStr(ref s, _) => {
+                        attr::mark_used(attribute);
+                        Some(s.get().to_string())
+                    },
+                    _ => {
+                        cx.span_warn(span, "Expected a string literal for `#[name = ...]`");
+                        name
+                    },
+                }
+            },
+            _ => name,
+        }
+    })
+}
+
+/// Scan the field's attributes for a `#[instance_rate = N]` override, the
+/// attribute's divisor for instanced rendering. Defaults to `0` (per-vertex).
+fn find_instance_rate(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
+                      attributes: &[ast::Attribute]) -> Gc<ast::Expr> {
+    let rate = attributes.iter().fold(None, |rate, attribute| {
+        match attribute.node.value.node {
+            ast::MetaNameValue(ref word, ref lit) if word.get() == "instance_rate" => {
+                match lit.node {
+                    ast::LitInt(n, _) | ast::LitIntUnsuffixed(n) => {
+                        attr::mark_used(attribute);
+                        Some(n)
+                    },
+                    ast::LitUint(n, _) => {
+                        attr::mark_used(attribute);
+                        Some(n as i64)
+                    },
+                    _ => {
+                        cx.span_warn(span,
+                            "Expected an integer literal for `#[instance_rate = ...]`");
+                        rate
+                    },
+                }
+            },
+            _ => rate,
+        }
+    });
+    cx.expr_lit(span, ast::LitIntUnsuffixed(rate.unwrap_or(0) as i64))
+}
+
 /// Find a `gfx::attrib::Type` that describes the given type identifier.
 fn decode_type(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
                ty_ident: &ast::Ident, modifier: Option<Modifier>) -> Gc<ast::Expr> {
@@ -98,7 +168,9 @@ fn decode_type(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
                         "Incompatible float modifier attribute: `#[{}]`", modifier
                     ).as_slice());
                     ""
-                }
+                },
+                Some(Skip) => cx.span_bug(span,
+                    "decode_type should never be reached for a #[skip] field"),
             });
             let sub_type = cx.ident_of(format!("F{}", ty_str.slice_from(1)).as_slice());
             quote_expr!(cx, gfx::attrib::Float(gfx::attrib::$kind,
@@ -106,19 +178,25 @@ fn decode_type(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
         },
         "u8" | "u16" | "u32" | "u64" | "uint" |
         "i8" | "i16" | "i32" | "i64" | "int" => {
-            let sign = cx.ident_of({
-                if ty_str.starts_with("i") { "Signed" } else { "Unsigned" }
-            });
-            let kind = cx.ident_of(match modifier {
-                None => "IntRaw",
-                Some(Normalized) => "IntNormalized",
-                Some(AsFloat) => "IntAsFloat",
-                Some(AsDouble) => {
+            let sign = if ty_str.starts_with("i") { Signed } else { Unsigned };
+            // (modifier, sign) as one explicit, exhaustive table.
+            let kind = match (modifier, sign) {
+                (None, _) => "IntRaw",
+                (Some(Normalized), _) => "IntNormalized",
+                (Some(AsFloat), _) => "IntAsFloat",
+                (Some(AsDouble), _) => {
                     cx.span_warn(span, format!(
                         "Incompatible int modifier attribute: `#[{}]`", modifier
                     ).as_slice());
                     ""
-                }
+                },
+                (Some(Skip), _) => cx.span_bug(span,
+                    "decode_type should never be reached for a #[skip] field"),
+            };
+            let kind = cx.ident_of(kind);
+            let sign = cx.ident_of(match sign {
+                Signed => "Signed",
+                Unsigned => "Unsigned",
             });
             let sub_type = cx.ident_of(format!("U{}", ty_str.slice_from(1)).as_slice());
             quote_expr!(cx, gfx::attrib::Int(gfx::attrib::$kind,
@@ -132,94 +210,270 @@ fn decode_type(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
     }
 }
 
+/// The attribute slot(s) a single struct field expands to. Most fields are a
+/// single scalar or vector, but a matrix field (a fixed-length vector of
+/// fixed-length vectors, e.g. `[[f32, ..4], ..4]`) consumes one attribute per
+/// column, since that's how GLSL exposes `matNxM` inputs.
+#[deriving(Clone)]
+enum AttributeShape {
+    /// `(elem_count, elem_type)` for a single `gfx::Attribute`.
+    Scalar(Gc<ast::Expr>, Gc<ast::Expr>),
+    /// `(columns, elem_count, elem_type, elem_type_ident)` describing
+    /// `columns` consecutive attributes, each `elem_count` wide.
+    Matrix(uint, Gc<ast::Expr>, Gc<ast::Expr>, ast::Ident),
+}
+
+/// Evaluate a fixed-length vector's size expression down to a `uint`, since
+/// the number of attribute slots a matrix field expands to must be known
+/// while the macro itself is expanding.
+fn expr_to_uint(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
+                expr: Gc<ast::Expr>) -> uint {
+    match expr.node {
+        ast::ExprLit(lit) => match lit.node {
+            ast::LitInt(n, _) => n as uint,
+            ast::LitIntUnsuffixed(n) => n as uint,
+            ast::LitUint(n, _) => n as uint,
+            _ => {
+                cx.span_err(span, "Expected an integer literal as a fixed-length vector size");
+                0
+            },
+        },
+        _ => {
+            cx.span_err(span, "Expected an integer literal as a fixed-length vector size");
+            0
+        },
+    }
+}
+
 fn decode_count_and_type(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
-                         field: &ast::StructField) -> (Gc<ast::Expr>, Gc<ast::Expr>) {
-    let modifier = find_modifier(cx, span, field.node.attrs.as_slice());
+                         field: &ast::StructField, modifier: Option<Modifier>) -> AttributeShape {
     match field.node.ty.node {
-        ast::TyPath(ref p, _, _) => (
+        ast::TyPath(ref p, _, _) => Scalar(
             cx.expr_lit(span, ast::LitIntUnsuffixed(1)),
             decode_type(cx, span, &p.segments[0].identifier, modifier),
         ),
-        ast::TyFixedLengthVec(pty, expr) => (expr, match pty.node {
-            ast::TyPath(ref p, _, _) => {
-                decode_type(cx, span, &p.segments[0].identifier, modifier)
+        ast::TyFixedLengthVec(pty, expr) => match pty.node {
+            ast::TyPath(ref p, _, _) => Scalar(
+                expr, decode_type(cx, span, &p.segments[0].identifier, modifier)
+            ),
+            ast::TyFixedLengthVec(inner_pty, inner_expr) => match inner_pty.node {
+                ast::TyPath(ref p, _, _) => {
+                    let columns = expr_to_uint(cx, span, expr);
+                    Matrix(columns, inner_expr,
+                           decode_type(cx, span, &p.segments[0].identifier, modifier),
+                           p.segments[0].identifier)
+                },
+                _ => {
+                    cx.span_err(span, format!("Unsupported matrix column type: \
+                                              `{}`", inner_pty.node).as_slice());
+                    Scalar(cx.expr_lit(span, ast::LitNil), cx.expr_lit(span, ast::LitNil))
+                },
             },
             _ => {
                 cx.span_err(span, format!("Unsupported fixed vector sub-type: \
                                           `{}`",pty.node).as_slice());
-                cx.expr_lit(span, ast::LitNil)
+                Scalar(cx.expr_lit(span, ast::LitNil), cx.expr_lit(span, ast::LitNil))
             },
-        }),
+        },
         _ => {
             cx.span_err(span, format!("Unsupported attribute type: `{}`",
                                       field.node.ty.node).as_slice());
-            (cx.expr_lit(span, ast::LitNil), cx.expr_lit(span, ast::LitNil))
+            Scalar(cx.expr_lit(span, ast::LitNil), cx.expr_lit(span, ast::LitNil))
         },
     }
 }
 
-fn offset_expr(cx: &mut ext::base::ExtCtxt, _: codemap::Span,
-                    struct_ident: ast::Ident, field_ident: ast::Ident) -> Gc<ast::Expr> {
-    quote_expr!(cx, unsafe {
-        &(*(0u as *const $struct_ident)).$field_ident as *const _ as gfx::attrib::Offset
-    })
+/// A way of referring back to a field of the struct being derived on, used to
+/// build the pointer arithmetic in `offset_expr` for both named and tuple
+/// structs.
+#[deriving(Clone)]
+enum FieldAccess {
+    /// A named field, accessed as `value.field`.
+    ByName(ast::Ident),
+    /// A positional field of a tuple struct, accessed as `value.0`.
+    ByIndex(uint),
+}
+
+fn offset_expr(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
+                    struct_ident: ast::Ident, access: FieldAccess) -> Gc<ast::Expr> {
+    match access {
+        ByName(field_ident) => quote_expr!(cx, unsafe {
+            &(*(0u as *const $struct_ident)).$field_ident as *const _ as gfx::attrib::Offset
+        }),
+        ByIndex(index) => {
+            let base = quote_expr!(cx, *(0u as *const $struct_ident));
+            let field = cx.expr(span, ast::ExprTupField(base, codemap::respan(span, index)));
+            quote_expr!(cx, unsafe { &$field as *const _ as gfx::attrib::Offset })
+        },
+    }
 }
 
 fn stride_expr(cx: &mut ext::base::ExtCtxt, struct_ident: ast::Ident) -> Gc<ast::Expr> {
     quote_expr!(cx, std::mem::size_of::<$struct_ident>() as gfx::attrib::Stride)
 }
 
-/// Generates the the method body for `gfx::VertexFormat::generate`.
-fn method_body(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
-                   substr: &generic::Substructure) -> Gc<ast::Expr> {
-    match *substr.fields {
+/// Builds the owned-`String` expression used for an attribute's `name`.
+fn name_expr(cx: &mut ext::base::ExtCtxt, span: codemap::Span, name: String) -> Gc<ast::Expr> {
+    cx.expr_method_call(span,
+        cx.expr_str(span, token::intern_and_get_ident(name.as_slice())),
+        cx.ident_of("to_string"), Vec::new())
+}
+
+/// Builds a `gfx::Attribute` struct literal from its already-decoded pieces
+/// and pushes it onto the `at` vector being assembled in `method_body`.
+fn push_attribute(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
+                  statements: &mut Vec<ast::Stmt>, id_at: ast::Ident, buffer: Gc<ast::Expr>,
+                  count: Gc<ast::Expr>, ty: Gc<ast::Expr>, offset: Gc<ast::Expr>,
+                  stride: Gc<ast::Expr>, name: Gc<ast::Expr>, instance_rate: Gc<ast::Expr>) {
+    let ex_struct = cx.expr_struct(span,
+        cx.path(span, vec![
+            cx.ident_of("gfx"),
+            cx.ident_of("Attribute")
+            ]),
+        vec![
+            cx.field_imm(span, cx.ident_of("buffer"), buffer),
+            cx.field_imm(span, cx.ident_of("elem_count"), count),
+            cx.field_imm(span, cx.ident_of("elem_type"), ty),
+            cx.field_imm(span, cx.ident_of("offset"), offset),
+            cx.field_imm(span, cx.ident_of("stride"), stride),
+            cx.field_imm(span, cx.ident_of("name"), name),
+            cx.field_imm(span, cx.ident_of("instance_rate"), instance_rate),
+        ]
+    );
+    statements.push(cx.stmt_expr(cx.expr_method_call(
+        span,
+        cx.expr_ident(span, id_at),
+        cx.ident_of("push"),
+        vec![ex_struct]
+    )));
+}
+
+/// One field's fully-resolved contribution to the derived methods: how to
+/// access it on `self`, its name after any `#[name = ...]` override, its
+/// `#[instance_rate = ...]` (or the `0` default), and its attribute shape.
+/// `#[skip]`ped fields never make it into this list.
+struct FieldInfo {
+    access: FieldAccess,
+    name: String,
+    instance_rate: Gc<ast::Expr>,
+    shape: AttributeShape,
+}
+
+/// Resolves every non-skipped field of the struct being derived on into a
+/// `FieldInfo`, running `find_modifier`/`find_name_override`/
+/// `find_instance_rate`/`decode_count_and_type` (and whatever diagnostics
+/// they emit) exactly once per field. `generate` and `attribute_names` both
+/// consume this same list instead of each re-scanning the fields, so a
+/// malformed attribute doesn't get warned or errored on twice.
+fn decode_fields(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
+                 substr: &generic::Substructure) -> Vec<FieldInfo> {
+    let fields = match *substr.fields {
         generic::StaticStruct(ref definition, generic::Named(ref fields)) => {
-            let mut statements = Vec::new();
-            let id_at = cx.ident_of("at");
-            let ex_new = cx.expr_call(span, cx.expr_path(cx.path(span,
-                    vec![cx.ident_of("Vec"), cx.ident_of("with_capacity")]
-                )), vec![cx.expr_uint(span, fields.len())]
-            );
-            statements.push(cx.stmt_let(span, true, id_at, ex_new));
-            let ex_stride = stride_expr(cx, substr.type_ident);
-            for (def, &(ident, _)) in definition.fields.iter().zip(fields.iter()) {
-                let (ex_count, ex_type) = decode_count_and_type(cx, span, def);
-                let ex_offset = offset_expr(cx, span, substr.type_ident, ident);
-                let ex_struct = cx.expr_struct(span,
-                    cx.path(span, vec![
-                        cx.ident_of("gfx"),
-                        cx.ident_of("Attribute")
-                        ]),
-                    vec![
-                        cx.field_imm(span, cx.ident_of("buffer"), substr.nonself_args[1]),
-                        cx.field_imm(span, cx.ident_of("elem_count"), ex_count),
-                        cx.field_imm(span, cx.ident_of("elem_type"), ex_type),
-                        cx.field_imm(span, cx.ident_of("offset"), ex_offset),
-                        cx.field_imm(span, cx.ident_of("stride"), ex_stride),
-                        cx.field_imm(span, cx.ident_of("name"), cx.expr_method_call(span,
-                            cx.expr_str(span, token::get_ident(ident)),
-                            cx.ident_of("to_string"), Vec::new()))
-                    ]
-                );
-                statements.push(cx.stmt_expr(cx.expr_method_call(
-                    span,
-                    cx.expr_ident(span, id_at),
-                    cx.ident_of("push"),
-                    vec![ex_struct]
-                )));
-            }
-            cx.expr_block(cx.block_all(
-                span,
-                Vec::new(),
-                statements,
-                Some(cx.expr_ident(span, id_at))
-            ))
+            definition.fields.iter().zip(fields.iter())
+                .map(|(def, &(ident, _))| {
+                    (def, ByName(ident), token::get_ident(ident).get().to_string())
+                }).collect::<Vec<_>>()
+        },
+        generic::StaticStruct(ref definition, generic::Unnamed(_)) => {
+            definition.fields.iter().enumerate()
+                .map(|(i, def)| (def, ByIndex(i), i.to_string()))
+                .collect::<Vec<_>>()
         },
         _ => {
-            cx.span_err(span, "Unable to implement `generate()` on a non-structure");
-            cx.expr_lit(span, ast::LitNil)
+            cx.span_err(span, "Unable to derive `VertexFormat` on a non-structure");
+            Vec::new()
+        },
+    };
+    fields.into_iter().filter_map(|(def, access, default_name)| {
+        let modifier = find_modifier(cx, span, def.node.attrs.as_slice());
+        if modifier == Some(Skip) {
+            return None;
+        }
+        let name = find_name_override(cx, span, def.node.attrs.as_slice()).unwrap_or(default_name);
+        let instance_rate = find_instance_rate(cx, span, def.node.attrs.as_slice());
+        let shape = decode_count_and_type(cx, span, def, modifier);
+        Some(FieldInfo { access: access, name: name, instance_rate: instance_rate, shape: shape })
+    }).collect()
+}
+
+/// Generates the the method body for `gfx::VertexFormat::generate`.
+fn method_body(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
+                   substr: &generic::Substructure, fields: &[FieldInfo]) -> Gc<ast::Expr> {
+    let mut statements = Vec::new();
+    let id_at = cx.ident_of("at");
+    let ex_new = cx.expr_call(span, cx.expr_path(cx.path(span,
+            vec![cx.ident_of("Vec"), cx.ident_of("with_capacity")]
+        )), vec![cx.expr_uint(span, fields.len())]
+    );
+    statements.push(cx.stmt_let(span, true, id_at, ex_new));
+    let ex_stride = stride_expr(cx, substr.type_ident);
+    for field in fields.iter() {
+        let ex_offset = offset_expr(cx, span, substr.type_ident, field.access.clone());
+        match field.shape.clone() {
+            Scalar(ex_count, ex_type) => {
+                let ex_name = name_expr(cx, span, field.name.clone());
+                push_attribute(cx, span, &mut statements, id_at, substr.nonself_args[1],
+                               ex_count, ex_type, ex_offset, ex_stride, ex_name, field.instance_rate);
+            },
+            Matrix(columns, ex_count, ex_type, elem_ident) => {
+                // Each column of a matrix field lands in its own attribute
+                // slot, offset by `col * elem_count * size_of::<elem_ident>()`
+                // from the field's own (already-aligned) base offset.
+                for col in range(0u, columns) {
+                    let ex_col = cx.expr_uint(span, col);
+                    let ex_col_offset = quote_expr!(cx,
+                        $ex_offset + ($ex_col as gfx::attrib::Offset) *
+                        ($ex_count as gfx::attrib::Offset) *
+                        (std::mem::size_of::<$elem_ident>() as gfx::attrib::Offset));
+                    let ex_name = name_expr(cx, span, format!("{}_{}", field.name, col));
+                    push_attribute(cx, span, &mut statements, id_at, substr.nonself_args[1],
+                                   ex_count, ex_type, ex_col_offset, ex_stride, ex_name,
+                                   field.instance_rate);
+                }
+            },
         }
     }
+    cx.expr_block(cx.block_all(
+        span,
+        Vec::new(),
+        statements,
+        Some(cx.expr_ident(span, id_at))
+    ))
+}
+
+/// Generates the method body for `gfx::VertexFormat::attribute_names`, the
+/// same names `generate` would emit (including matrix fields' `_N` suffixes).
+fn attribute_names_body(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
+                        substr: &generic::Substructure, fields: &[FieldInfo]) -> Gc<ast::Expr> {
+    let mut statements = Vec::new();
+    let id_names = cx.ident_of("names");
+    let ex_new = cx.expr_call(span, cx.expr_path(cx.path(span,
+            vec![cx.ident_of("Vec"), cx.ident_of("with_capacity")]
+        )), vec![cx.expr_uint(span, fields.len())]
+    );
+    statements.push(cx.stmt_let(span, true, id_names, ex_new));
+    for field in fields.iter() {
+        match field.shape.clone() {
+            Scalar(_, _) => {
+                statements.push(cx.stmt_expr(cx.expr_method_call(span,
+                    cx.expr_ident(span, id_names), cx.ident_of("push"),
+                    vec![name_expr(cx, span, field.name.clone())])));
+            },
+            Matrix(columns, _, _, _) => {
+                for col in range(0u, columns) {
+                    statements.push(cx.stmt_expr(cx.expr_method_call(span,
+                        cx.expr_ident(span, id_names), cx.ident_of("push"),
+                        vec![name_expr(cx, span, format!("{}_{}", field.name, col))])));
+                }
+            },
+        }
+    }
+    cx.expr_block(cx.block_all(
+        span,
+        Vec::new(),
+        statements,
+        Some(cx.expr_ident(span, id_names))
+    ))
 }
 
 
@@ -227,6 +481,25 @@ fn method_body(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
 pub fn expand_vertex_format(context: &mut ext::base::ExtCtxt, span: codemap::Span,
                             meta_item: Gc<ast::MetaItem>, item: Gc<ast::Item>,
                             push: |Gc<ast::Item>|) {
+    // `generate` and `attribute_names` both need `decode_fields`'s result for
+    // the same struct; decode lazily on whichever method expands first and
+    // share it with the other via this cache, so its diagnostics fire once.
+    let fields_cache: Rc<RefCell<Option<Rc<Vec<FieldInfo>>>>> = Rc::new(RefCell::new(None));
+
+    let generate_cache = fields_cache.clone();
+    let generate_body = move |cx: &mut ext::base::ExtCtxt, span: codemap::Span,
+                              substr: &generic::Substructure| {
+        let fields = fields_for(cx, span, substr, &generate_cache);
+        method_body(cx, span, substr, fields.as_slice())
+    };
+
+    let names_cache = fields_cache.clone();
+    let attribute_names_body_ = move |cx: &mut ext::base::ExtCtxt, span: codemap::Span,
+                                      substr: &generic::Substructure| {
+        let fields = fields_for(cx, span, substr, &names_cache);
+        attribute_names_body(cx, span, substr, fields.as_slice())
+    };
+
     // `impl gfx::VertexFormat for $item`
     generic::TraitDef {
         span: span,
@@ -269,8 +542,94 @@ pub fn expand_vertex_format(context: &mut ext::base::ExtCtxt, span: codemap::Spa
                 ),
                 attributes: Vec::new(),
                 // generate the method body
-                combine_substructure: generic::combine_substructure(method_body),
+                combine_substructure: generic::combine_substructure(generate_body),
+            },
+            // `fn attribute_names(Option<Self>) -> Vec<String>`
+            generic::MethodDef {
+                name: "attribute_names",
+                generics: generic::ty::LifetimeBounds::empty(),
+                explicit_self: None,
+                args: vec![
+                    generic::ty::Literal(generic::ty::Path {
+                        path: vec!["Option"],
+                        lifetime: None,
+                        params: vec![box generic::ty::Self],
+                        global: false,
+                    }),
+                ],
+                ret_ty: generic::ty::Literal(
+                    generic::ty::Path {
+                        path: vec!["Vec"],
+                        lifetime: None,
+                        params: vec![
+                            box generic::ty::Literal(generic::ty::Path::new(
+                                vec!["String"])),
+                        ],
+                        global: false,
+                    },
+                ),
+                attributes: Vec::new(),
+                combine_substructure: generic::combine_substructure(attribute_names_body_),
             },
         ],
     }.expand(context, meta_item, item, push);
 }
+
+/// Returns the cached `decode_fields` result for this struct, computing and
+/// populating the cache on first use.
+fn fields_for(cx: &mut ext::base::ExtCtxt, span: codemap::Span,
+              substr: &generic::Substructure,
+              cache: &Rc<RefCell<Option<Rc<Vec<FieldInfo>>>>>) -> Rc<Vec<FieldInfo>> {
+    if let Some(ref fields) = *cache.borrow() {
+        return fields.clone();
+    }
+    let fields = Rc::new(decode_fields(cx, span, substr));
+    *cache.borrow_mut() = Some(fields.clone());
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_type, Modifier, Normalized, AsFloat};
+    use syntax::codemap::DUMMY_SP;
+    use syntax::ext::base::ExtCtxt;
+    use syntax::ext::expand::ExpansionConfig;
+    use syntax::ext::build::AstBuilder;
+    use syntax::parse::ParseSess;
+    use syntax::print::pprust;
+
+    fn decoded(ty: &str, modifier: Option<Modifier>) -> String {
+        let sess = ParseSess::new();
+        let mut cx = ExtCtxt::new(&sess, Vec::new(), ExpansionConfig::default("test".to_string()));
+        let ident = cx.ident_of(ty);
+        pprust::expr_to_string(&*decode_type(&mut cx, DUMMY_SP, &ident, modifier))
+    }
+
+    /// Every `{i8, i16, u8, u16} x {raw, normalized, as_float}` pairing,
+    /// locking down the generated `gfx::attrib::Int(...)` expression -
+    /// including the signed-normalized cases (e.g. `#[normalized] i8`) the
+    /// (modifier, sign) table exists to keep explicit.
+    #[test]
+    fn decode_type_int_matrix() {
+        let cases = vec![
+            ("i8", None, "IntRaw", "Signed"),
+            ("i8", Some(Normalized), "IntNormalized", "Signed"),
+            ("i8", Some(AsFloat), "IntAsFloat", "Signed"),
+            ("i16", None, "IntRaw", "Signed"),
+            ("i16", Some(Normalized), "IntNormalized", "Signed"),
+            ("i16", Some(AsFloat), "IntAsFloat", "Signed"),
+            ("u8", None, "IntRaw", "Unsigned"),
+            ("u8", Some(Normalized), "IntNormalized", "Unsigned"),
+            ("u8", Some(AsFloat), "IntAsFloat", "Unsigned"),
+            ("u16", None, "IntRaw", "Unsigned"),
+            ("u16", Some(Normalized), "IntNormalized", "Unsigned"),
+            ("u16", Some(AsFloat), "IntAsFloat", "Unsigned"),
+        ];
+        for (ty, modifier, kind, sign) in cases.into_iter() {
+            let sub_type = format!("U{}", ty.slice_from(1));
+            let expected = format!("gfx::attrib::Int(gfx::attrib::{}, gfx::attrib::{}, gfx::attrib::{})",
+                                    kind, sub_type, sign);
+            assert_eq!(decoded(ty, modifier), expected);
+        }
+    }
+}